@@ -0,0 +1,428 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::metrics::Metrics;
+use crate::sink::{BoxFuture, Sink};
+use crate::spool::Spool;
+
+/// Retry knobs for delivering a single batch (see [`BetterStackLayerBuilder`]).
+///
+/// [`BetterStackLayerBuilder`]: crate::BetterStackLayerBuilder
+pub(crate) struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    pub(crate) backoff_base: Duration,
+    pub(crate) backoff_cap: Duration,
+}
+
+/// Compression codec applied to a batch's JSON body before it's POSTed.
+///
+/// See [`BetterStackLayerBuilder::compression`].
+///
+/// [`BetterStackLayerBuilder::compression`]: crate::BetterStackLayerBuilder::compression
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Codec {
+    /// Send the JSON body as-is (default).
+    #[default]
+    None,
+    /// Gzip the JSON body and send it with `Content-Encoding: gzip`.
+    Gzip,
+    /// Zstd-compress the JSON body and send it with `Content-Encoding: zstd`.
+    Zstd,
+}
+
+/// The built-in [`Sink`] that POSTs batches to the Better Stack HTTP
+/// ingestion API, with retries, a circuit breaker, and an optional durable
+/// spool for batches it can't deliver.
+pub(crate) struct BetterStackSink {
+    client: reqwest::Client,
+    endpoint: String,
+    source_token: String,
+    retry_policy: RetryPolicy,
+    codec: Codec,
+    spool: Option<Arc<Spool>>,
+    breaker: Mutex<CircuitBreaker>,
+    metrics: Arc<Metrics>,
+}
+
+impl BetterStackSink {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        source_token: String,
+        endpoint: String,
+        retry_policy: RetryPolicy,
+        codec: Codec,
+        spool: Option<Arc<Spool>>,
+        failure_threshold: u32,
+        breaker_cooldown: Duration,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+
+        Self {
+            client,
+            endpoint,
+            source_token,
+            retry_policy,
+            codec,
+            spool,
+            breaker: Mutex::new(CircuitBreaker::new(failure_threshold, breaker_cooldown)),
+            metrics,
+        }
+    }
+
+    /// Replay any spooled segments before this sink starts shipping live
+    /// batches. Called once from `run_shipper` at startup, then
+    /// periodically while running. Skipped while the circuit breaker is
+    /// open, so a persistent outage doesn't get hammered by both the live
+    /// path and replay at once.
+    pub(crate) async fn replay_spool(&self) {
+        if let Some(spool) = &self.spool {
+            replay_spool(
+                &self.client,
+                &self.endpoint,
+                &self.source_token,
+                spool,
+                &self.retry_policy,
+                self.codec,
+                &self.metrics,
+                &self.breaker,
+            )
+            .await;
+        }
+    }
+}
+
+impl Sink for BetterStackSink {
+    fn ship<'a>(&'a self, events: &'a [Value]) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            if events.is_empty() {
+                return;
+            }
+
+            if self.breaker.lock().unwrap().is_open() {
+                self.metrics.record_batch_failed();
+                spool_or_drop(self.spool.as_deref(), events, "circuit breaker is open");
+                return;
+            }
+
+            match post_batch_with_retry(
+                &self.client,
+                &self.endpoint,
+                &self.source_token,
+                events,
+                &self.retry_policy,
+                self.codec,
+            )
+            .await
+            {
+                Some(bytes_sent) => {
+                    self.breaker.lock().unwrap().record_success();
+                    self.metrics.record_batch_shipped(events.len() as u64, bytes_sent);
+                }
+                None => {
+                    self.breaker.lock().unwrap().record_failure();
+                    self.metrics.record_batch_failed();
+                    spool_or_drop(self.spool.as_deref(), events, "undelivered batch");
+                }
+            }
+        })
+    }
+}
+
+fn spool_or_drop(spool: Option<&Spool>, events: &[Value], reason: &str) {
+    if events.is_empty() {
+        return;
+    }
+    match spool {
+        Some(spool) => {
+            if let Err(err) = spool.write_batch(events) {
+                eprintln!("[lasersell-tel] failed to spool {reason}: {err}");
+            }
+        }
+        None => {
+            eprintln!(
+                "[lasersell-tel] dropping {reason} ({} events, no spool configured)",
+                events.len()
+            );
+        }
+    }
+}
+
+/// Outcome of a single POST attempt.
+enum PostOutcome {
+    Success,
+    /// Worth retrying (a network error, a 429, or a 5xx). `retry_after` is
+    /// set when the server sent a `Retry-After` header.
+    Retryable {
+        status: Option<StatusCode>,
+        retry_after: Option<Duration>,
+    },
+    /// A non-retryable 4xx — the batch should be dropped, not retried.
+    ClientError(StatusCode),
+}
+
+/// POST a batch of events, retrying retryable failures with exponential
+/// backoff (plus jitter) up to `retry_policy.max_retries`, honoring a
+/// server-supplied `Retry-After` on 429 instead of the computed backoff.
+/// Returns the number of (possibly compressed) bytes sent once a 2xx
+/// response is received, `None` once the batch is dropped (client error,
+/// or a body encoding failure) or retries are exhausted.
+async fn post_batch_with_retry(
+    client: &reqwest::Client,
+    endpoint: &str,
+    source_token: &str,
+    events: &[Value],
+    retry_policy: &RetryPolicy,
+    codec: Codec,
+) -> Option<u64> {
+    if events.is_empty() {
+        return Some(0);
+    }
+
+    let body = match encode_batch(events, codec) {
+        Ok(body) => body,
+        Err(err) => {
+            eprintln!("[lasersell-tel] failed to encode batch: {err}");
+            return None;
+        }
+    };
+    let bytes_sent = body.bytes.len() as u64;
+
+    let mut attempt = 0u32;
+    loop {
+        match post_batch_once(client, endpoint, source_token, &body).await {
+            PostOutcome::Success => return Some(bytes_sent),
+            PostOutcome::ClientError(status) => {
+                eprintln!("[lasersell-tel] Better Stack rejected batch with HTTP {status}, dropping");
+                return None;
+            }
+            PostOutcome::Retryable { status, retry_after } => {
+                attempt += 1;
+                if attempt > retry_policy.max_retries {
+                    eprintln!(
+                        "[lasersell-tel] giving up on batch after {attempt} attempts (last status: {status:?})"
+                    );
+                    return None;
+                }
+                let delay = retry_after.unwrap_or_else(|| backoff_delay(retry_policy, attempt));
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// A batch's JSON body, already compressed per the configured [`Codec`].
+struct EncodedBody {
+    bytes: Vec<u8>,
+    content_encoding: Option<&'static str>,
+}
+
+/// Serialize `events` to JSON and compress the result per `codec`.
+fn encode_batch(events: &[Value], codec: Codec) -> std::io::Result<EncodedBody> {
+    let json = serde_json::to_vec(events)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+
+    match codec {
+        Codec::None => Ok(EncodedBody {
+            bytes: json,
+            content_encoding: None,
+        }),
+        Codec::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&json)?;
+            Ok(EncodedBody {
+                bytes: encoder.finish()?,
+                content_encoding: Some("gzip"),
+            })
+        }
+        Codec::Zstd => {
+            let bytes = zstd::stream::encode_all(json.as_slice(), 0)?;
+            Ok(EncodedBody {
+                bytes,
+                content_encoding: Some("zstd"),
+            })
+        }
+    }
+}
+
+async fn post_batch_once(
+    client: &reqwest::Client,
+    endpoint: &str,
+    source_token: &str,
+    body: &EncodedBody,
+) -> PostOutcome {
+    let mut request = client
+        .post(endpoint)
+        .bearer_auth(source_token)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(content_encoding) = body.content_encoding {
+        request = request.header(reqwest::header::CONTENT_ENCODING, content_encoding);
+    }
+
+    let resp = match request.body(body.bytes.clone()).send().await {
+        Ok(resp) => resp,
+        Err(err) => {
+            eprintln!("[lasersell-tel] failed to ship logs to Better Stack: {err}");
+            return PostOutcome::Retryable {
+                status: None,
+                retry_after: None,
+            };
+        }
+    };
+
+    let status = resp.status();
+    if status.is_success() {
+        return PostOutcome::Success;
+    }
+
+    eprintln!("[lasersell-tel] Better Stack returned HTTP {status}");
+
+    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        let retry_after = parse_retry_after(&resp);
+        PostOutcome::Retryable {
+            status: Some(status),
+            retry_after,
+        }
+    } else {
+        PostOutcome::ClientError(status)
+    }
+}
+
+/// Parse a `Retry-After` header expressed as a number of seconds (the
+/// HTTP-date form isn't expected from Better Stack, so it's not handled).
+fn parse_retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    resp.headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Exponential backoff with full jitter: a random duration in `[0, cap]`,
+/// where `cap` doubles `backoff_base` per attempt up to `backoff_cap`.
+fn backoff_delay(retry_policy: &RetryPolicy, attempt: u32) -> Duration {
+    let shift = attempt.saturating_sub(1).min(31);
+    let exp = retry_policy
+        .backoff_base
+        .checked_mul(1u32 << shift)
+        .unwrap_or(retry_policy.backoff_cap);
+    let capped = exp.min(retry_policy.backoff_cap);
+    capped.mul_f64(jitter_fraction())
+}
+
+/// A cheap pseudo-random fraction in `[0, 1)` derived from the clock. Good
+/// enough to spread out retries — no need for a full RNG dependency here.
+fn jitter_fraction() -> f64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    (nanos % 1_000_000) as f64 / 1_000_000.0
+}
+
+/// Tracks consecutive flush failures and opens a cool-down window once
+/// `failure_threshold` is reached, so a persistent outage doesn't get
+/// hammered with retries. Once the cool-down elapses, the next flush acts
+/// as a single probe: success closes the breaker, failure reopens it.
+struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+            consecutive_failures: 0,
+            open_until: None,
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.open_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+    }
+
+    fn record_failure(&mut self) {
+        self.consecutive_failures += 1;
+        if self.consecutive_failures >= self.failure_threshold {
+            self.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+/// Replay spooled segments in sequence order before the live channel is
+/// drained, deleting each segment only once its batch is acknowledged.
+/// Stops at the first failure so a persistent outage doesn't spin — the
+/// remaining segments stay on disk and are retried the next time this runs
+/// (called once at startup, then periodically by `run_shipper`). Recorded
+/// on `metrics` the same way a live `ship()` would be, so operators
+/// watching delivery counters see replayed batches too. Skips entirely
+/// while `breaker` is open, so replay doesn't keep hammering the endpoint
+/// during its cool-down — the next attempt (once the breaker's cool-down
+/// elapses) acts as its probe, same as a live flush would.
+async fn replay_spool(
+    client: &reqwest::Client,
+    endpoint: &str,
+    source_token: &str,
+    spool: &Spool,
+    retry_policy: &RetryPolicy,
+    codec: Codec,
+    metrics: &Metrics,
+    breaker: &Mutex<CircuitBreaker>,
+) {
+    if breaker.lock().unwrap().is_open() {
+        return;
+    }
+
+    let segments = match spool.pending_segments() {
+        Ok(segments) => segments,
+        Err(err) => {
+            eprintln!("[lasersell-tel] failed to read spool directory: {err}");
+            return;
+        }
+    };
+
+    for path in segments {
+        let events = match Spool::read_segment(&path) {
+            Ok(events) => events,
+            Err(err) => {
+                eprintln!("[lasersell-tel] failed to read spool segment {path:?}: {err}");
+                continue;
+            }
+        };
+
+        match post_batch_with_retry(client, endpoint, source_token, &events, retry_policy, codec)
+            .await
+        {
+            Some(bytes_sent) => {
+                breaker.lock().unwrap().record_success();
+                metrics.record_batch_shipped(events.len() as u64, bytes_sent);
+                if let Err(err) = spool.remove_segment(&path) {
+                    eprintln!("[lasersell-tel] failed to remove spool segment {path:?}: {err}");
+                }
+            }
+            None => {
+                breaker.lock().unwrap().record_failure();
+                metrics.record_batch_failed();
+                eprintln!("[lasersell-tel] stopping spool replay after a failed batch");
+                break;
+            }
+        }
+    }
+}
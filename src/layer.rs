@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use serde_json::Value;
@@ -9,20 +11,52 @@ use tracing_subscriber::layer::Context;
 use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::Layer;
 
+use crate::betterstack_sink::{BetterStackSink, Codec, RetryPolicy};
+use crate::metrics::{Metrics, MetricsSnapshot};
 use crate::shipper::run_shipper;
-use crate::{DEFAULT_BATCH_SIZE, DEFAULT_CHANNEL_CAPACITY, DEFAULT_ENDPOINT, DEFAULT_FLUSH_INTERVAL};
-
-/// A [`tracing::Layer`] that ships structured JSON log events to Better Stack.
+use crate::sink::Sink;
+use crate::spool::Spool;
+use crate::time::chrono_now_iso;
+use crate::{
+    DEFAULT_BATCH_SIZE, DEFAULT_BREAKER_COOLDOWN, DEFAULT_CHANNEL_CAPACITY, DEFAULT_ENDPOINT,
+    DEFAULT_FAILURE_THRESHOLD, DEFAULT_FLUSH_INTERVAL, DEFAULT_MAX_RETRIES,
+    DEFAULT_OVERFLOW_CHANNEL_CAPACITY, DEFAULT_RETRY_BACKOFF_BASE, DEFAULT_RETRY_BACKOFF_CAP,
+    DEFAULT_SPOOL_MAX_BYTES, DEFAULT_SPOOL_REPLAY_INTERVAL,
+};
+
+/// A [`tracing::Layer`] that ships structured JSON log events to Better
+/// Stack, and optionally to any other registered [`Sink`].
 ///
 /// Events are sent through a bounded channel to a background tokio task that
-/// batches and POSTs them. If the channel is full, events are silently dropped
-/// so request handlers are never blocked.
+/// batches them and fans each batch out to every sink. If the channel is
+/// full, events are handed off (without blocking the caller) to a background
+/// spooler task that writes them to the write-ahead spool (if configured),
+/// so a burst doesn't silently drop events a later replay could still
+/// deliver.
 pub struct BetterStackLayer {
     tx: mpsc::Sender<Value>,
+    /// Hands overflow events (the live channel was full) to the background
+    /// spooler task, so writing the segment file never happens on the
+    /// caller's thread. `None` when no spool is configured.
+    overflow_tx: Option<mpsc::Sender<Value>>,
+    /// Self-observability counters (events received/dropped, batches
+    /// shipped/failed, bytes sent).
+    metrics: Arc<Metrics>,
+    /// Whether to also emit the legacy single-innermost-span `span` field
+    /// alongside the full `spans` chain.
+    legacy_span_field: bool,
     /// Keep handle so the shipper task is cancelled on drop.
     _shutdown: tokio::sync::oneshot::Sender<()>,
 }
 
+impl BetterStackLayer {
+    /// A cheap point-in-time copy of this layer's self-observability
+    /// counters.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+}
+
 /// Builder for [`BetterStackLayer`].
 pub struct BetterStackLayerBuilder {
     source_token: String,
@@ -30,6 +64,15 @@ pub struct BetterStackLayerBuilder {
     channel_capacity: usize,
     batch_size: usize,
     flush_interval: Duration,
+    spool_dir: Option<PathBuf>,
+    spool_max_bytes: u64,
+    max_retries: u32,
+    retry_backoff_cap: Duration,
+    failure_threshold: u32,
+    compression: Codec,
+    sinks: Vec<Arc<dyn Sink>>,
+    metrics_log_interval: Option<Duration>,
+    legacy_span_field: bool,
 }
 
 impl BetterStackLayerBuilder {
@@ -41,6 +84,15 @@ impl BetterStackLayerBuilder {
             channel_capacity: DEFAULT_CHANNEL_CAPACITY,
             batch_size: DEFAULT_BATCH_SIZE,
             flush_interval: DEFAULT_FLUSH_INTERVAL,
+            spool_dir: None,
+            spool_max_bytes: DEFAULT_SPOOL_MAX_BYTES,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_backoff_cap: DEFAULT_RETRY_BACKOFF_CAP,
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            compression: Codec::None,
+            sinks: Vec::new(),
+            metrics_log_interval: None,
+            legacy_span_field: true,
         }
     }
 
@@ -68,6 +120,102 @@ impl BetterStackLayerBuilder {
         self
     }
 
+    /// Enable a durable write-ahead spool under `dir`: batches that can't be
+    /// sent right away (the channel is full, or a `flush` POST fails) are
+    /// written there and replayed at startup and periodically thereafter
+    /// (every 30s), so they survive a crash or a sustained Better Stack
+    /// outage instead of being lost.
+    ///
+    /// The spool only covers delivery to Better Stack. Replayed events are
+    /// not fanned out to sinks registered via [`sink`](Self::sink) — a
+    /// [`RotatingFileSink`](crate::RotatingFileSink) added for a durable
+    /// local copy will not see events that overflowed onto the spool. A
+    /// batch spooled after a failed POST was already handed to those sinks
+    /// with its original flush; its replay goes to Better Stack only.
+    pub fn spool_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spool_dir = Some(dir.into());
+        self
+    }
+
+    /// Cap the spool directory's total size (default: 256 MiB). Once
+    /// exceeded, the oldest segments are evicted to make room for new ones.
+    /// `0` disables the cap.
+    pub fn spool_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.spool_max_bytes = max_bytes;
+        self
+    }
+
+    /// Set the max number of retry attempts for a retryable (429 or 5xx)
+    /// batch before it's handed to the spool, or dropped if no spool is
+    /// configured (default: 5).
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Cap the exponential backoff delay between retries (default: 30s).
+    /// The delay starts at 250ms and doubles per attempt, up to this cap,
+    /// with jitter applied.
+    pub fn retry_backoff_cap(mut self, cap: Duration) -> Self {
+        self.retry_backoff_cap = cap;
+        self
+    }
+
+    /// Set how many consecutive failed flushes open the circuit breaker
+    /// (default: 5). While open, batches are spooled (or dropped) instead
+    /// of POSTed, until a single probe batch succeeds.
+    pub fn failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold;
+        self
+    }
+
+    /// Compress each batch's JSON body before POSTing it to Better Stack,
+    /// setting the matching `Content-Encoding` header (default: no
+    /// compression). Cuts egress bandwidth for high-volume, highly
+    /// compressible JSON payloads.
+    pub fn compression(mut self, codec: Codec) -> Self {
+        self.compression = codec;
+        self
+    }
+
+    /// Register an additional [`Sink`] that every batch is also fanned out
+    /// to, alongside the built-in Better Stack sink. Useful for keeping a
+    /// local copy of logs (see [`StdoutSink`] and [`RotatingFileSink`])
+    /// without stacking multiple tracing layers.
+    ///
+    /// Only the live batch path is fanned out this way. The write-ahead
+    /// spool (see [`spool_dir`](Self::spool_dir)) is owned by the built-in
+    /// Better Stack sink alone, so events recovered from it — whether
+    /// overflowed onto the spool or retried after a failed flush — are
+    /// replayed to Better Stack only, never to sinks registered here.
+    ///
+    /// [`StdoutSink`]: crate::StdoutSink
+    /// [`RotatingFileSink`]: crate::RotatingFileSink
+    pub fn sink(mut self, sink: impl Sink + 'static) -> Self {
+        self.sinks.push(Arc::new(sink));
+        self
+    }
+
+    /// Periodically emit the layer's own [`MetricsSnapshot`] counters as an
+    /// internal log event (target `lasersell_tel::metrics`), so drop/failure
+    /// rates flow through the same sinks as everything else. Off by
+    /// default.
+    pub fn metrics_log_interval(mut self, interval: Duration) -> Self {
+        self.metrics_log_interval = Some(interval);
+        self
+    }
+
+    /// Whether to also emit the pre-0.2 `span` field — the innermost span
+    /// alone, in the same shape as each entry of `spans` — alongside the
+    /// full `spans` chain. On by default, so upgrading doesn't silently
+    /// drop `span` out from under consumers that haven't migrated to
+    /// reading `spans` yet. Call `.legacy_span_field(false)` once nothing
+    /// reads the single-span shape anymore.
+    pub fn legacy_span_field(mut self, enabled: bool) -> Self {
+        self.legacy_span_field = enabled;
+        self
+    }
+
     /// Build the layer and spawn the background shipper task.
     ///
     /// Requires a running tokio runtime.
@@ -75,17 +223,67 @@ impl BetterStackLayerBuilder {
         let (tx, rx) = mpsc::channel(self.channel_capacity);
         let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
 
+        let spool = self.spool_dir.and_then(|dir| {
+            match Spool::open(dir, self.spool_max_bytes) {
+                Ok(spool) => Some(Arc::new(spool)),
+                Err(err) => {
+                    eprintln!("[lasersell-tel] failed to open spool directory: {err}");
+                    None
+                }
+            }
+        });
+
+        // Overflow events (channel full) are handed off to a dedicated
+        // background task so the segment write (open/write/flush/rename)
+        // never runs on the caller's thread.
+        let overflow_tx = spool.clone().map(|spool| {
+            let (overflow_tx, overflow_rx) = mpsc::channel(DEFAULT_OVERFLOW_CHANNEL_CAPACITY);
+            tokio::spawn(crate::spool::run_overflow_spooler(overflow_rx, spool));
+            overflow_tx
+        });
+
+        let retry_policy = RetryPolicy {
+            max_retries: self.max_retries,
+            backoff_base: DEFAULT_RETRY_BACKOFF_BASE,
+            backoff_cap: self.retry_backoff_cap,
+        };
+
+        let metrics = Arc::new(Metrics::default());
+
+        let betterstack = Arc::new(BetterStackSink::new(
+            self.source_token,
+            self.endpoint,
+            retry_policy,
+            self.compression,
+            spool.clone(),
+            self.failure_threshold,
+            DEFAULT_BREAKER_COOLDOWN,
+            Arc::clone(&metrics),
+        ));
+
         tokio::spawn(run_shipper(
             rx,
             shutdown_rx,
-            self.source_token,
-            self.endpoint,
+            betterstack,
+            self.sinks,
             self.batch_size,
             self.flush_interval,
+            DEFAULT_SPOOL_REPLAY_INTERVAL,
         ));
 
+        if let Some(interval) = self.metrics_log_interval {
+            tokio::spawn(crate::metrics::emit_periodically(
+                tx.clone(),
+                Arc::clone(&metrics),
+                interval,
+            ));
+        }
+
         BetterStackLayer {
             tx,
+            overflow_tx,
+            metrics,
+            legacy_span_field: self.legacy_span_field,
             _shutdown: shutdown_tx,
         }
     }
@@ -143,6 +341,38 @@ struct SpanData {
     fields: serde_json::Map<String, Value>,
 }
 
+/// Walk the event's full span scope root-to-leaf, returning one JSON object
+/// per span (`name` plus that span's own recorded fields, with `name`
+/// always the span's real name even if a recorded field happens to be
+/// called `name`) alongside a flattened map of all those fields merged
+/// ancestor-to-descendant, so a descendant's value wins on a collision.
+fn collect_span_chain<S>(
+    ctx: &Context<'_, S>,
+    event: &tracing::Event<'_>,
+) -> (Vec<Value>, serde_json::Map<String, Value>)
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let mut merged_fields = serde_json::Map::new();
+    let Some(scope) = ctx.event_scope(event) else {
+        return (Vec::new(), merged_fields);
+    };
+    let chain = scope
+        .from_root()
+        .filter_map(|span| {
+            let ext = span.extensions();
+            ext.get::<SpanData>().map(|data| {
+                merged_fields.extend(data.fields.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+                let mut map = data.fields.clone();
+                map.insert("name".to_string(), Value::String(data.name.clone()));
+                Value::Object(map)
+            })
+        })
+        .collect();
+    (chain, merged_fields)
+}
+
 impl<S> Layer<S> for BetterStackLayer
 where
     S: Subscriber + for<'a> LookupSpan<'a>,
@@ -184,18 +414,12 @@ where
             .remove("message")
             .unwrap_or_else(|| Value::String(String::new()));
 
-        // Collect innermost span info
-        let span_json = ctx.event_span(event).and_then(|span| {
-            let ext = span.extensions();
-            ext.get::<SpanData>().map(|data| {
-                let mut map = serde_json::Map::new();
-                map.insert("name".to_string(), Value::String(data.name.clone()));
-                for (k, v) in &data.fields {
-                    map.insert(k.clone(), v.clone());
-                }
-                Value::Object(map)
-            })
-        });
+        // Walk the full span scope, root to leaf, so parent-span fields
+        // (e.g. a `request_id` set on an outer span) aren't lost. `fields`
+        // merges ancestor to descendant, with the event's own fields
+        // winning over both.
+        let (span_chain, mut fields) = collect_span_chain(&ctx, event);
+        fields.extend(visitor.fields);
 
         let now = chrono_now_iso();
 
@@ -211,59 +435,39 @@ where
             Value::String(meta.target().to_string()),
         );
 
-        if !visitor.fields.is_empty() {
-            obj.insert("fields".to_string(), Value::Object(visitor.fields));
+        if !fields.is_empty() {
+            obj.insert("fields".to_string(), Value::Object(fields));
         }
 
-        if let Some(span) = span_json {
-            obj.insert("span".to_string(), span);
+        // Kept for backward compatibility: the innermost span alone, in the
+        // pre-`spans` shape.
+        if self.legacy_span_field {
+            if let Some(innermost) = span_chain.last() {
+                obj.insert("span".to_string(), innermost.clone());
+            }
         }
 
-        // Non-blocking send — drop the event if the channel is full.
-        let _ = self.tx.try_send(Value::Object(obj));
-    }
-}
+        if !span_chain.is_empty() {
+            obj.insert("spans".to_string(), Value::Array(span_chain));
+        }
 
-/// Produce an ISO 8601 timestamp without pulling in the `chrono` crate.
-fn chrono_now_iso() -> String {
-    // Use std SystemTime → format manually.
-    let now = std::time::SystemTime::now();
-    let dur = now
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default();
-    let secs = dur.as_secs();
-    let millis = dur.subsec_millis();
-
-    // Convert epoch seconds to a simple ISO string.
-    // We avoid pulling in chrono by doing the math ourselves.
-    const SECS_PER_DAY: u64 = 86400;
-    let days = secs / SECS_PER_DAY;
-    let day_secs = secs % SECS_PER_DAY;
-    let hours = day_secs / 3600;
-    let minutes = (day_secs % 3600) / 60;
-    let seconds = day_secs % 60;
-
-    // Days since epoch to Y-M-D (civil calendar).
-    let (year, month, day) = days_to_ymd(days);
-
-    format!(
-        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
-        year, month, day, hours, minutes, seconds, millis
-    )
+        // Non-blocking send — fall back to handing the event to the
+        // background spooler task (if configured) rather than blocking or
+        // dropping it outright when the channel is full.
+        match self.tx.try_send(Value::Object(obj)) {
+            Ok(()) => self.metrics.record_received(),
+            Err(err) => {
+                self.metrics.record_dropped_channel_full();
+                if let Some(overflow_tx) = &self.overflow_tx {
+                    let event = err.into_inner();
+                    if overflow_tx.try_send(event).is_err() {
+                        eprintln!(
+                            "[lasersell-tel] overflow spooler is backed up; dropping event"
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
 
-/// Convert days since Unix epoch to (year, month, day).
-fn days_to_ymd(days: u64) -> (i64, u64, u64) {
-    // Algorithm from Howard Hinnant's `chrono`-compatible civil calendar code.
-    let z = days as i64 + 719468;
-    let era = if z >= 0 { z } else { z - 146096 } / 146097;
-    let doe = (z - era * 146097) as u64;
-    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
-    let y = yoe as i64 + era * 400;
-    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
-    let mp = (5 * doy + 2) / 153;
-    let d = doy - (153 * mp + 2) / 5 + 1;
-    let m = if mp < 10 { mp + 3 } else { mp - 9 };
-    let y = if m <= 2 { y + 1 } else { y };
-    (y, m, d)
-}
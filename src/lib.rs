@@ -1,7 +1,19 @@
+mod betterstack_sink;
 mod layer;
+mod metrics;
+mod rotating_file_sink;
 mod shipper;
+mod sink;
+mod spool;
+mod stdout_sink;
+mod time;
 
+pub use betterstack_sink::Codec;
 pub use layer::{BetterStackLayer, BetterStackLayerBuilder};
+pub use metrics::MetricsSnapshot;
+pub use rotating_file_sink::{RotatingFileSink, RotationPolicy};
+pub use sink::Sink;
+pub use stdout_sink::{StdoutFormat, StdoutSink};
 
 use std::time::Duration;
 
@@ -25,3 +37,26 @@ pub(crate) const DEFAULT_ENDPOINT: &str = "https://in.logs.betterstack.com";
 pub(crate) const DEFAULT_CHANNEL_CAPACITY: usize = 8192;
 pub(crate) const DEFAULT_BATCH_SIZE: usize = 100;
 pub(crate) const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// Default cap on total spool directory size (256 MiB) before the oldest
+/// segments are evicted.
+pub(crate) const DEFAULT_SPOOL_MAX_BYTES: u64 = 256 * 1024 * 1024;
+/// Default max retries (after the first attempt) for a retryable batch
+/// before it's handed to the spool (or dropped, if no spool is configured).
+pub(crate) const DEFAULT_MAX_RETRIES: u32 = 5;
+/// Default base delay for exponential backoff between retries.
+pub(crate) const DEFAULT_RETRY_BACKOFF_BASE: Duration = Duration::from_millis(250);
+/// Default cap on the backoff delay between retries.
+pub(crate) const DEFAULT_RETRY_BACKOFF_CAP: Duration = Duration::from_secs(30);
+/// Default number of consecutive failed flushes before the circuit breaker
+/// opens.
+pub(crate) const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+/// Default cool-down window while the circuit breaker is open.
+pub(crate) const DEFAULT_BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+/// Default capacity of the channel feeding the background overflow spooler
+/// task (distinct from the main event channel), so a burst of drops can't
+/// unbounded-queue in memory either.
+pub(crate) const DEFAULT_OVERFLOW_CHANNEL_CAPACITY: usize = 1024;
+/// Default interval at which the running shipper re-replays any spooled
+/// segments, so events spooled during a sustained overload (not just a
+/// crash/restart) still get delivered without needing a process restart.
+pub(crate) const DEFAULT_SPOOL_REPLAY_INTERVAL: Duration = Duration::from_secs(30);
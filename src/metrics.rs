@@ -0,0 +1,120 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::time::chrono_now_iso;
+
+/// Shared counters tracking the layer's own delivery behavior, so
+/// operators have a programmatic signal to alert on instead of relying on
+/// stderr logs.
+#[derive(Default)]
+pub(crate) struct Metrics {
+    events_received: AtomicU64,
+    events_dropped_channel_full: AtomicU64,
+    batches_shipped: AtomicU64,
+    events_shipped: AtomicU64,
+    batches_failed: AtomicU64,
+    bytes_sent: AtomicU64,
+}
+
+impl Metrics {
+    pub(crate) fn record_received(&self) {
+        self.events_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_dropped_channel_full(&self) {
+        self.events_dropped_channel_full.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_batch_shipped(&self, event_count: u64, bytes: u64) {
+        self.batches_shipped.fetch_add(1, Ordering::Relaxed);
+        self.events_shipped.fetch_add(event_count, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_batch_failed(&self) {
+        self.batches_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            events_received: self.events_received.load(Ordering::Relaxed),
+            events_dropped_channel_full: self.events_dropped_channel_full.load(Ordering::Relaxed),
+            batches_shipped: self.batches_shipped.load(Ordering::Relaxed),
+            events_shipped: self.events_shipped.load(Ordering::Relaxed),
+            batches_failed: self.batches_failed.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A cheap point-in-time copy of [`Metrics`], returned by
+/// [`BetterStackLayer::metrics`].
+///
+/// [`BetterStackLayer::metrics`]: crate::BetterStackLayer::metrics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// Events successfully enqueued onto the live channel.
+    pub events_received: u64,
+    /// Events dropped because the live channel was full (spooled instead,
+    /// if a spool is configured).
+    pub events_dropped_channel_full: u64,
+    /// Batches that were POSTed to Better Stack successfully.
+    pub batches_shipped: u64,
+    /// Events contained in successfully shipped batches.
+    pub events_shipped: u64,
+    /// Batches that exhausted retries, were rejected as client errors, or
+    /// were turned away while the circuit breaker was open, without a
+    /// successful delivery.
+    pub batches_failed: u64,
+    /// Bytes of request body (after compression, if any) POSTed in
+    /// successfully shipped batches.
+    pub bytes_sent: u64,
+}
+
+impl MetricsSnapshot {
+    /// Render the snapshot as a log event in the same shape as
+    /// `BetterStackLayer::on_event` produces, so it ships through the
+    /// layer's own sinks like any other event.
+    fn as_log_event(&self) -> Value {
+        let mut fields = serde_json::Map::new();
+        fields.insert("events_received".to_string(), Value::from(self.events_received));
+        fields.insert(
+            "events_dropped_channel_full".to_string(),
+            Value::from(self.events_dropped_channel_full),
+        );
+        fields.insert("batches_shipped".to_string(), Value::from(self.batches_shipped));
+        fields.insert("events_shipped".to_string(), Value::from(self.events_shipped));
+        fields.insert("batches_failed".to_string(), Value::from(self.batches_failed));
+        fields.insert("bytes_sent".to_string(), Value::from(self.bytes_sent));
+
+        let mut obj = serde_json::Map::new();
+        obj.insert("dt".to_string(), Value::String(chrono_now_iso()));
+        obj.insert("level".to_string(), Value::String("INFO".to_string()));
+        obj.insert(
+            "message".to_string(),
+            Value::String("lasersell-tel self-observability metrics".to_string()),
+        );
+        obj.insert("target".to_string(), Value::String("lasersell_tel::metrics".to_string()));
+        obj.insert("fields".to_string(), Value::Object(fields));
+        Value::Object(obj)
+    }
+}
+
+/// Periodically emit the counters as their own internal log event, so they
+/// flow through the same sinks as everything else.
+pub(crate) async fn emit_periodically(tx: mpsc::Sender<Value>, metrics: Arc<Metrics>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await; // first tick completes immediately — skip it.
+    loop {
+        ticker.tick().await;
+        let event = metrics.snapshot().as_log_event();
+        if tx.send(event).await.is_err() {
+            // Channel closed — the shipper task has shut down.
+            return;
+        }
+    }
+}
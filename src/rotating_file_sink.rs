@@ -0,0 +1,157 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+use crate::sink::{BoxFuture, Sink};
+use crate::time::days_to_ymd;
+
+/// Default number of rotated files to keep before the oldest are deleted.
+const DEFAULT_RETENTION: usize = 7;
+
+/// How often [`RotatingFileSink`] starts a new log file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationPolicy {
+    /// Roll over once the current file exceeds this many bytes.
+    Size(u64),
+    /// Roll over whenever the UTC day changes.
+    Daily,
+}
+
+/// A [`Sink`] that writes NDJSON (one JSON object per line) to a file
+/// under `dir`, rotating by size or by day and keeping only the most
+/// recent `retention` files.
+pub struct RotatingFileSink {
+    dir: PathBuf,
+    prefix: String,
+    policy: RotationPolicy,
+    retention: usize,
+    state: Mutex<RotationState>,
+}
+
+struct RotationState {
+    file: Option<File>,
+    bytes_written: u64,
+    day: Option<(i64, u64, u64)>,
+    seq: u64,
+}
+
+impl RotatingFileSink {
+    /// Create a sink writing NDJSON under `dir`, with filenames starting
+    /// with `prefix`.
+    pub fn new(dir: impl Into<PathBuf>, prefix: impl Into<String>, policy: RotationPolicy) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: prefix.into(),
+            policy,
+            retention: DEFAULT_RETENTION,
+            state: Mutex::new(RotationState {
+                file: None,
+                bytes_written: 0,
+                day: None,
+                seq: 0,
+            }),
+        }
+    }
+
+    /// Keep only the `retention` most recently rotated files (default: 7).
+    /// `0` disables retention cleanup.
+    pub fn retention(mut self, retention: usize) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    fn write_events(&self, events: &[Value]) -> std::io::Result<()> {
+        let today = current_date();
+        let mut state = self.state.lock().unwrap();
+
+        if self.needs_rotation(&state, today) {
+            self.rotate(&mut state, today)?;
+        }
+
+        let file = state.file.as_mut().expect("rotate always opens a file");
+        for event in events {
+            let mut line = serde_json::to_vec(event)?;
+            line.push(b'\n');
+            file.write_all(&line)?;
+            state.bytes_written += line.len() as u64;
+        }
+        file.flush()
+    }
+
+    fn needs_rotation(&self, state: &RotationState, today: (i64, u64, u64)) -> bool {
+        if state.file.is_none() {
+            return true;
+        }
+        match self.policy {
+            RotationPolicy::Daily => state.day != Some(today),
+            RotationPolicy::Size(max_bytes) => state.bytes_written >= max_bytes,
+        }
+    }
+
+    fn rotate(&self, state: &mut RotationState, today: (i64, u64, u64)) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        state.seq += 1;
+        let (year, month, day) = today;
+        let path = self.dir.join(format!(
+            "{}-{year:04}-{month:02}-{day:02}-{:04}.ndjson",
+            self.prefix, state.seq
+        ));
+
+        state.file = Some(OpenOptions::new().create(true).append(true).open(&path)?);
+        state.bytes_written = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        state.day = Some(today);
+
+        self.enforce_retention()
+    }
+
+    /// Delete the oldest rotated files once there are more than
+    /// `retention` of them.
+    fn enforce_retention(&self) -> std::io::Result<()> {
+        if self.retention == 0 {
+            return Ok(());
+        }
+
+        let file_prefix = format!("{}-", self.prefix);
+        let mut files: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(&file_prefix))
+            })
+            .collect();
+        files.sort();
+
+        if files.len() > self.retention {
+            for path in &files[..files.len() - self.retention] {
+                let _ = fs::remove_file(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn current_date() -> (i64, u64, u64) {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    days_to_ymd(secs / 86400)
+}
+
+impl Sink for RotatingFileSink {
+    fn ship<'a>(&'a self, events: &'a [Value]) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            if events.is_empty() {
+                return;
+            }
+            if let Err(err) = self.write_events(events) {
+                eprintln!("[lasersell-tel] failed to write rotating log file: {err}");
+            }
+        })
+    }
+}
@@ -1,29 +1,45 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use serde_json::Value;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 
-/// Background task that receives log events from the channel, batches them,
-/// and POSTs JSON arrays to the Better Stack HTTP ingestion API.
+use crate::betterstack_sink::BetterStackSink;
+use crate::sink::Sink;
+
+/// Background task that receives log events from the channel, batches
+/// them, and fans each batch out to every registered [`Sink`] (the
+/// built-in Better Stack sink plus any the user registered).
+///
+/// Besides the initial replay at startup, the Better Stack sink's spool is
+/// re-replayed on `spool_replay_interval`, so events spooled during a
+/// sustained overload (channel full, or repeated flush failures) still get
+/// delivered as the outage clears, instead of sitting on disk until the
+/// process happens to restart.
 pub(crate) async fn run_shipper(
     mut rx: mpsc::Receiver<Value>,
     mut shutdown: oneshot::Receiver<()>,
-    source_token: String,
-    endpoint: String,
+    betterstack: Arc<BetterStackSink>,
+    extra_sinks: Vec<Arc<dyn Sink>>,
     batch_size: usize,
     flush_interval: Duration,
+    spool_replay_interval: Duration,
 ) {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(10))
-        .build()
-        .unwrap_or_else(|_| reqwest::Client::new());
+    betterstack.replay_spool().await;
+
+    let sinks: Vec<Arc<dyn Sink>> = std::iter::once(betterstack.clone() as Arc<dyn Sink>)
+        .chain(extra_sinks)
+        .collect();
 
     let mut batch: Vec<Value> = Vec::with_capacity(batch_size);
     let mut interval = tokio::time::interval(flush_interval);
     // The first tick completes immediately — skip it.
     interval.tick().await;
 
+    let mut replay_interval = tokio::time::interval(spool_replay_interval);
+    replay_interval.tick().await;
+
     loop {
         tokio::select! {
             biased;
@@ -34,11 +50,11 @@ pub(crate) async fn run_shipper(
                 while let Ok(event) = rx.try_recv() {
                     batch.push(event);
                     if batch.len() >= batch_size {
-                        flush(&client, &endpoint, &source_token, &mut batch).await;
+                        flush(&sinks, &mut batch).await;
                     }
                 }
                 if !batch.is_empty() {
-                    flush(&client, &endpoint, &source_token, &mut batch).await;
+                    flush(&sinks, &mut batch).await;
                 }
                 return;
             }
@@ -49,13 +65,13 @@ pub(crate) async fn run_shipper(
                     Some(event) => {
                         batch.push(event);
                         if batch.len() >= batch_size {
-                            flush(&client, &endpoint, &source_token, &mut batch).await;
+                            flush(&sinks, &mut batch).await;
                         }
                     }
                     // Channel closed — flush and exit.
                     None => {
                         if !batch.is_empty() {
-                            flush(&client, &endpoint, &source_token, &mut batch).await;
+                            flush(&sinks, &mut batch).await;
                         }
                         return;
                     }
@@ -65,36 +81,33 @@ pub(crate) async fn run_shipper(
             // Timer tick — flush whatever we have.
             _ = interval.tick() => {
                 if !batch.is_empty() {
-                    flush(&client, &endpoint, &source_token, &mut batch).await;
+                    flush(&sinks, &mut batch).await;
                 }
             }
+
+            // Periodically re-replay the spool, so events that piled up
+            // there during an outage or a sustained overflow get delivered
+            // without waiting for a process restart.
+            _ = replay_interval.tick() => {
+                betterstack.replay_spool().await;
+            }
         }
     }
 }
 
-async fn flush(
-    client: &reqwest::Client,
-    endpoint: &str,
-    source_token: &str,
-    batch: &mut Vec<Value>,
-) {
-    let events: Vec<Value> = batch.drain(..).collect();
-    match client
-        .post(endpoint)
-        .bearer_auth(source_token)
-        .json(&events)
-        .send()
-        .await
-    {
-        Ok(resp) if !resp.status().is_success() => {
-            eprintln!(
-                "[lasersell-tel] Better Stack returned HTTP {}",
-                resp.status()
-            );
-        }
-        Err(err) => {
-            eprintln!("[lasersell-tel] failed to ship logs to Better Stack: {err}");
-        }
-        _ => {}
+/// Drain the batch and ship it to every sink concurrently.
+async fn flush(sinks: &[Arc<dyn Sink>], batch: &mut Vec<Value>) {
+    let events: Arc<[Value]> = batch.drain(..).collect::<Vec<_>>().into();
+
+    let mut handles = Vec::with_capacity(sinks.len());
+    for sink in sinks {
+        let sink = Arc::clone(sink);
+        let events = Arc::clone(&events);
+        handles.push(tokio::spawn(
+            async move { sink.ship(&events).await },
+        ));
+    }
+    for handle in handles {
+        let _ = handle.await;
     }
 }
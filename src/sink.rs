@@ -0,0 +1,19 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use serde_json::Value;
+
+/// A boxed, type-erased future, used so [`Sink::ship`] can be an async
+/// trait method without pulling in an async-trait crate.
+pub(crate) type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A destination for batches of JSON log events.
+///
+/// `ship` never returns an error — a sink that wants to avoid losing
+/// events on a delivery failure (like the built-in Better Stack sink) is
+/// responsible for retrying, buffering, or spooling internally.
+pub trait Sink: Send + Sync {
+    /// Deliver a batch of events. Called once per flush with everything
+    /// accumulated since the last flush.
+    fn ship<'a>(&'a self, events: &'a [Value]) -> BoxFuture<'a, ()>;
+}
@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// On-disk write-ahead spool for batches that could not be delivered
+/// immediately (the live channel was full, or a `flush` POST failed), so
+/// they survive a crash or restart instead of being lost.
+///
+/// Each spooled batch is stored as its own segment file under `dir`, named
+/// by a monotonically increasing sequence number, zero-padded to 20 digits
+/// (`00000000000000000001.seg`).
+/// A segment holds one or more length-prefixed JSON records back to back,
+/// so a batch can be read back without needing a surrounding JSON array.
+pub(crate) struct Spool {
+    dir: PathBuf,
+    next_seq: AtomicU64,
+    max_bytes: u64,
+    /// Pending segments (oldest first) with their size in bytes, kept in
+    /// sync with the filesystem on every write/remove so eviction never
+    /// needs to re-`read_dir`/`stat` the whole directory.
+    state: Mutex<SpoolState>,
+}
+
+struct SpoolState {
+    segments: VecDeque<(u64, u64)>,
+    total_bytes: u64,
+}
+
+impl Spool {
+    /// Open (creating if needed) a spool directory, picking up numbering
+    /// after the highest sequence number already present.
+    pub(crate) fn open(dir: PathBuf, max_bytes: u64) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let mut segments: Vec<(u64, u64)> = fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let seq = seq_from_path(&path)?;
+                let len = fs::metadata(&path).ok()?.len();
+                Some((seq, len))
+            })
+            .collect();
+        segments.sort_by_key(|(seq, _)| *seq);
+
+        let next_seq = segments.last().map_or(0, |(seq, _)| seq + 1);
+        let total_bytes = segments.iter().map(|(_, len)| len).sum();
+
+        Ok(Self {
+            dir,
+            next_seq: AtomicU64::new(next_seq),
+            max_bytes,
+            state: Mutex::new(SpoolState {
+                segments: segments.into(),
+                total_bytes,
+            }),
+        })
+    }
+
+    /// Serialize `events` to a new segment file, then evict the oldest
+    /// segments if the spool now exceeds `max_bytes`.
+    pub(crate) fn write_batch(&self, events: &[Value]) -> io::Result<()> {
+        let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+        let path = self.segment_path(seq);
+        let tmp_path = path.with_extension("seg.tmp");
+
+        let mut written = 0u64;
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            for event in events {
+                let bytes = serde_json::to_vec(event)?;
+                writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+                writer.write_all(&bytes)?;
+                written += 4 + bytes.len() as u64;
+            }
+            writer.flush()?;
+        }
+        fs::rename(&tmp_path, &path)?;
+
+        let mut state = self.state.lock().unwrap();
+        state.segments.push_back((seq, written));
+        state.total_bytes += written;
+        self.enforce_max_bytes(&mut state);
+        Ok(())
+    }
+
+    /// Pending segment paths, oldest (lowest sequence number) first.
+    pub(crate) fn pending_segments(&self) -> io::Result<Vec<PathBuf>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .segments
+            .iter()
+            .map(|(seq, _)| self.segment_path(*seq))
+            .collect())
+    }
+
+    /// Read back a segment's events in the order they were written.
+    pub(crate) fn read_segment(path: &Path) -> io::Result<Vec<Value>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut events = Vec::new();
+        loop {
+            let mut len_buf = [0u8; 4];
+            match reader.read_exact(&mut len_buf) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            }
+            let mut buf = vec![0u8; u32::from_be_bytes(len_buf) as usize];
+            reader.read_exact(&mut buf)?;
+            if let Ok(value) = serde_json::from_slice(&buf) {
+                events.push(value);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Delete a segment once its batch has been acknowledged.
+    pub(crate) fn remove_segment(&self, path: &Path) -> io::Result<()> {
+        match fs::remove_file(path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+
+        if let Some(seq) = seq_from_path(path) {
+            let mut state = self.state.lock().unwrap();
+            if let Some(pos) = state.segments.iter().position(|(s, _)| *s == seq) {
+                let (_, len) = state.segments.remove(pos).unwrap();
+                state.total_bytes = state.total_bytes.saturating_sub(len);
+            }
+        }
+        Ok(())
+    }
+
+    fn segment_path(&self, seq: u64) -> PathBuf {
+        self.dir.join(format!("{seq:020}.seg"))
+    }
+
+    /// Evict the oldest segments until the spool's total size (tracked
+    /// incrementally in `state`, not re-derived from the filesystem) is
+    /// back under `max_bytes`, so a permanent outage can't fill the disk.
+    /// `max_bytes == 0` disables the cap.
+    fn enforce_max_bytes(&self, state: &mut SpoolState) {
+        if self.max_bytes == 0 {
+            return;
+        }
+
+        while state.total_bytes > self.max_bytes {
+            let Some((seq, len)) = state.segments.pop_front() else {
+                break;
+            };
+            // Drop it from accounting regardless of whether the remove
+            // succeeded, so a missing/permission-denied file can't make us
+            // spin on the same entry forever.
+            let _ = fs::remove_file(self.segment_path(seq));
+            state.total_bytes = state.total_bytes.saturating_sub(len);
+        }
+    }
+}
+
+fn seq_from_path(path: &Path) -> Option<u64> {
+    if path.extension().and_then(|ext| ext.to_str()) != Some("seg") {
+        return None;
+    }
+    path.file_stem()?.to_str()?.parse().ok()
+}
+
+/// Background task that owns all overflow writes to the spool, so the
+/// segment file create/write/flush/rename (and any resulting eviction)
+/// never runs on the caller's thread. Fed by `BetterStackLayer::on_event`
+/// when the main event channel is full.
+///
+/// Coalesces whatever's already queued (up to [`DEFAULT_BATCH_SIZE`]) into
+/// one `write_batch` call per segment, rather than one segment — and later
+/// one replay POST — per overflowed event.
+///
+/// [`DEFAULT_BATCH_SIZE`]: crate::DEFAULT_BATCH_SIZE
+pub(crate) async fn run_overflow_spooler(mut rx: mpsc::Receiver<Value>, spool: Arc<Spool>) {
+    while let Some(first) = rx.recv().await {
+        let mut batch = vec![first];
+        while batch.len() < crate::DEFAULT_BATCH_SIZE {
+            match rx.try_recv() {
+                Ok(event) => batch.push(event),
+                Err(_) => break,
+            }
+        }
+
+        let count = batch.len();
+        if let Err(err) = spool.write_batch(&batch) {
+            eprintln!("[lasersell-tel] failed to spool {count} overflow event(s): {err}");
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use serde_json::Value;
+
+use crate::sink::{BoxFuture, Sink};
+
+/// Output format used by [`StdoutSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StdoutFormat {
+    /// One compact JSON object per line (newline-delimited JSON).
+    Ndjson,
+    /// One indented, human-readable JSON object per event.
+    Pretty,
+}
+
+/// A [`Sink`] that prints each event to stdout, for local development.
+pub struct StdoutSink {
+    format: StdoutFormat,
+}
+
+impl StdoutSink {
+    /// Create a sink that writes newline-delimited JSON (the default).
+    pub fn new() -> Self {
+        Self {
+            format: StdoutFormat::Ndjson,
+        }
+    }
+
+    /// Pretty-print each event instead of compact NDJSON.
+    pub fn pretty(mut self) -> Self {
+        self.format = StdoutFormat::Pretty;
+        self
+    }
+}
+
+impl Default for StdoutSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sink for StdoutSink {
+    fn ship<'a>(&'a self, events: &'a [Value]) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            for event in events {
+                let printed = match self.format {
+                    StdoutFormat::Ndjson => serde_json::to_string(event),
+                    StdoutFormat::Pretty => serde_json::to_string_pretty(event),
+                };
+                if let Ok(text) = printed {
+                    println!("{text}");
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,47 @@
+//! Small date/time helpers shared by the layer and the sinks, written by
+//! hand so the crate doesn't need to pull in `chrono` for simple
+//! timestamp and calendar-date formatting.
+
+/// Produce an ISO 8601 timestamp without pulling in the `chrono` crate.
+pub(crate) fn chrono_now_iso() -> String {
+    // Use std SystemTime → format manually.
+    let now = std::time::SystemTime::now();
+    let dur = now
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = dur.as_secs();
+    let millis = dur.subsec_millis();
+
+    // Convert epoch seconds to a simple ISO string.
+    // We avoid pulling in chrono by doing the math ourselves.
+    const SECS_PER_DAY: u64 = 86400;
+    let days = secs / SECS_PER_DAY;
+    let day_secs = secs % SECS_PER_DAY;
+    let hours = day_secs / 3600;
+    let minutes = (day_secs % 3600) / 60;
+    let seconds = day_secs % 60;
+
+    // Days since epoch to Y-M-D (civil calendar).
+    let (year, month, day) = days_to_ymd(days);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year, month, day, hours, minutes, seconds, millis
+    )
+}
+
+/// Convert days since Unix epoch to (year, month, day).
+pub(crate) fn days_to_ymd(days: u64) -> (i64, u64, u64) {
+    // Algorithm from Howard Hinnant's `chrono`-compatible civil calendar code.
+    let z = days as i64 + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = (z - era * 146097) as u64;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let y = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = if m <= 2 { y + 1 } else { y };
+    (y, m, d)
+}